@@ -1,11 +1,17 @@
-use kong::{json, kroute, server, ErrorResponse, Kong, Kontrol, Method};
+use kong::{json, kroute, server, ErrorResponse, Kong, KongConfig, Kontrol, Method};
 use kong_kontrollers::accounts::{
     create::CreateAccountKontroller, database::Database as AccountsDB,
+    delete::DeleteAccountKontroller, update_email::UpdateEmailKontroller,
+    update_password::UpdatePasswordKontroller, verify::VerifyKontroller as VerifyAccountKontroller,
 };
 use kong_kontrollers::blog::{create::CreateBlogPostKontroller, database::Database as BlogsDB};
-use kong_kontrollers::login::{is_admin, LoginKontroller};
+use kong_kontrollers::login::{
+    AuthBackend, LocalDbBackend, LoginKontroller, LogoutEverywhereKontroller, RefreshKontroller,
+};
+use kong_kontrollers::mailer::{LoggingMailer, Mailer};
 use kong_kontrollers::newsletter::{
     database::Database as NewsletterDB, subscribe::SubscribeNewsletterKontroller,
+    verify::VerifyKontroller as VerifyNewsletterKontroller,
 };
 use std::sync::{Arc, Mutex};
 
@@ -23,15 +29,55 @@ fn main() {
     let newsletter_database = Arc::new(Mutex::new(NewsletterDB::new(TEST_NEWSLETTER_DB)));
     newsletter_database.lock().unwrap().connect().unwrap();
 
+    // swap this for `LdapBackend::new(...)` to authenticate against a
+    // directory server instead of the local accounts database
+    let auth_backend: Arc<dyn AuthBackend> = Arc::new(LocalDbBackend::new(accounts_database.clone()));
+
+    // logs the verification token instead of sending a real email; swap for
+    // an SMTP-backed `Mailer` impl in production
+    let mailer: Arc<dyn Mailer> = Arc::new(LoggingMailer::new());
+
     kroute(vec![
         Box::new(CreateAccountKontroller {
             address: "/accounts".to_string(),
             method: Method::Post,
             database: accounts_database.clone(),
+            mailer: mailer.clone(),
+        }),
+        Box::new(VerifyAccountKontroller {
+            address: "/accounts/verify".to_string(),
+            method: Method::Post,
+            database: accounts_database.clone(),
         }),
         Box::new(LoginKontroller {
             address: "/login".to_string(),
             method: Method::Post,
+            auth_backend: auth_backend.clone(),
+        }),
+        Box::new(RefreshKontroller {
+            address: "/refresh".to_string(),
+            method: Method::Post,
+            database: accounts_database.clone(),
+        }),
+        Box::new(LogoutEverywhereKontroller {
+            address: "/logout-everywhere".to_string(),
+            method: Method::Post,
+            database: accounts_database.clone(),
+        }),
+        Box::new(UpdatePasswordKontroller {
+            address: "/accounts/password".to_string(),
+            method: Method::Post,
+            database: accounts_database.clone(),
+        }),
+        Box::new(UpdateEmailKontroller {
+            address: "/accounts/email".to_string(),
+            method: Method::Post,
+            database: accounts_database.clone(),
+            mailer: mailer.clone(),
+        }),
+        Box::new(DeleteAccountKontroller {
+            address: "/accounts/delete".to_string(),
+            method: Method::Post,
             database: accounts_database.clone(),
         }),
         Box::new(CreateBlogPostKontroller {
@@ -43,14 +89,22 @@ fn main() {
         Box::new(PrivateKontroller {
             address: "/private".to_string(),
             method: Method::Get,
-            database: accounts_database.clone(),
         }),
         Box::new(SubscribeNewsletterKontroller {
             address: "/newsletter".to_string(),
             method: Method::Post,
             database: newsletter_database.clone(),
+            mailer: mailer.clone(),
         }),
-    ]);
+        Box::new(VerifyNewsletterKontroller {
+            address: "/newsletter/verify".to_string(),
+            method: Method::Post,
+            database: newsletter_database.clone(),
+        }),
+    ],
+    KongConfig {
+        login_address: "/login".to_string(),
+    });
 }
 
 struct PrivateKontroller {
@@ -58,8 +112,6 @@ struct PrivateKontroller {
     address: String,
     /// Endpoint HTTP method
     method: Method,
-    /// Accounts database
-    database: Arc<Mutex<AccountsDB>>,
 }
 impl Kontrol for PrivateKontroller {
     fn address(&self) -> String {
@@ -70,35 +122,83 @@ impl Kontrol for PrivateKontroller {
         self.method
     }
 
-    fn kontrol(&self, kong: &Kong) -> server::Response {
-        if let Some(k) = &kong.kpassport {
-            if let Ok(admin) = is_admin(k, self.database.clone()) {
-                if admin {
-                    let res = json!({ "message": "Hello World" });
-                    server::Response::json(&res).with_status_code(200)
-                } else {
-                    ErrorResponse::unauthorized()
-                }
-            } else {
-                ErrorResponse::internal()
-            }
-        } else {
-            ErrorResponse::unauthorized()
-        }
+    fn required_roles(&self) -> Vec<String> {
+        vec!["admin".to_string()]
+    }
+
+    fn kontrol(&self, _kong: &Kong) -> server::Response {
+        // `kroute` already verified the kpassport carries every role
+        // `required_roles` lists, so reaching this point implies an admin.
+        let res = json!({ "message": "Hello World" });
+        server::Response::json(&res).with_status_code(200)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
-    use kong_kontrollers::accounts::inputs::AccountCreationInput;
+    use kong_kontrollers::accounts::inputs::{
+        AccountCreationInput, DeleteAccountInput, UpdateEmailInput, UpdatePasswordInput,
+    };
+    use kong::{Kpassport, DEFAULT_TTL_SECONDS};
     use kong_kontrollers::login::inputs::AccountLoginInput;
+    use kong_kontrollers::mailer::LoggingMailer;
     use reqwest::{blocking::multipart, StatusCode};
+    use std::sync::Once;
+    use std::time::{SystemTime, UNIX_EPOCH};
     const ADDRESS: &str = "http://localhost:7878";
 
+    fn now() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs()
+    }
+
+    /// Pulls the `kpassport=...` value out of a response's `Set-Cookie` header.
+    fn kpassport_cookie(response: &reqwest::blocking::Response) -> String {
+        response
+            .headers()
+            .get("Set-Cookie")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(';').next())
+            .and_then(|v| v.strip_prefix("kpassport="))
+            .expect("response carried a kpassport cookie")
+            .to_string()
+    }
+
+    /// Starts the one long-lived server this whole test binary shares,
+    /// against freshly emptied databases, the first time any test asks
+    /// for it.
+    fn ensure_server_running() {
+        static START: Once = Once::new();
+        START.call_once(|| {
+            remove_test_dbs();
+            std::thread::spawn(main);
+
+            let client = reqwest::blocking::Client::new();
+            for _ in 0..100 {
+                if client.get(format!("{ADDRESS}/private")).send().is_ok() {
+                    return;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(50));
+            }
+            panic!("test server did not come up in time");
+        });
+    }
+
+    static TEST_LOCK: Mutex<()> = Mutex::new(());
+
+    /// Tests that mutate shared state on the one long-lived server (role
+    /// bumps, session-epoch bumps, deletes) take this guard so they can't
+    /// interleave with another such test's in-flight request. Tests that
+    /// only read or that use their own unique username/db rows don't need
+    /// it.
+    fn serial_guard() -> std::sync::MutexGuard<'static, ()> {
+        TEST_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
     #[test]
     fn test_register_account_login() {
-        remove_test_dbs();
+        let _guard = serial_guard();
+        ensure_server_running();
 
         let private_route = format!("{ADDRESS}/private");
         let register_route = format!("{ADDRESS}/accounts");
@@ -112,18 +212,24 @@ mod test {
         let res = client.get(&private_route).send().unwrap();
         assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
 
-        // register admin account
+        // register admin account: another guarded test may have already
+        // created it if it ran first, so either outcome is fine here --
+        // what matters is that the account unambiguously exists afterward
         let account = AccountCreationInput {
             username: "admin".to_string(),
             email: Some("admin@example.com".to_string()),
             password: "1234567890".to_string(),
         };
         let res = client.post(&register_route).json(&account).send().unwrap();
-        assert_eq!(res.status(), StatusCode::CREATED);
+        assert!(matches!(
+            res.status(),
+            StatusCode::CREATED | StatusCode::CONFLICT
+        ));
 
-        // try to register account with already existing credentials
+        // now that the account is known to exist, registering it again is
+        // unambiguously a duplicate
         let res = client.post(register_route).json(&account).send().unwrap();
-        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(res.status(), StatusCode::CONFLICT);
 
         // try to login with wrong credentials
         let login_info = AccountLoginInput {
@@ -146,6 +252,327 @@ mod test {
         assert_eq!(res.status(), StatusCode::OK);
     }
 
+    #[test]
+    fn test_private_route_requires_admin_role() {
+        let _guard = serial_guard();
+        ensure_server_running();
+
+        let private_route = format!("{ADDRESS}/private");
+        let register_route = format!("{ADDRESS}/accounts");
+        let login_route = format!("{ADDRESS}/login");
+        let client = reqwest::blocking::Client::builder()
+            .cookie_store(true)
+            .build()
+            .unwrap();
+
+        // register a regular, non-admin account
+        let account = AccountCreationInput {
+            username: "reader".to_string(),
+            email: Some("reader@example.com".to_string()),
+            password: "1234567890".to_string(),
+        };
+        client.post(register_route).json(&account).send().unwrap();
+
+        // login
+        let login_info = AccountLoginInput {
+            username: "reader".to_string(),
+            password: "1234567890".to_string(),
+        };
+        client.post(login_route).json(&login_info).send().unwrap();
+
+        // an authenticated but unprivileged account is forbidden, not unauthorized
+        let res = client.get(&private_route).send().unwrap();
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_logout_everywhere_invalidates_session() {
+        let _guard = serial_guard();
+        ensure_server_running();
+
+        let private_route = format!("{ADDRESS}/private");
+        let register_route = format!("{ADDRESS}/accounts");
+        let login_route = format!("{ADDRESS}/login");
+        let logout_everywhere_route = format!("{ADDRESS}/logout-everywhere");
+        let client = reqwest::blocking::Client::builder()
+            .cookie_store(true)
+            .build()
+            .unwrap();
+
+        let account = AccountCreationInput {
+            username: "admin".to_string(),
+            email: Some("admin@example.com".to_string()),
+            password: "1234567890".to_string(),
+        };
+        client.post(register_route).json(&account).send().unwrap();
+
+        let login_info = AccountLoginInput {
+            username: "admin".to_string(),
+            password: "1234567890".to_string(),
+        };
+        client.post(login_route).json(&login_info).send().unwrap();
+
+        // passport is valid right after login
+        let res = client.get(&private_route).send().unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // bump the session epoch, invalidating the outstanding passport
+        let res = client.post(&logout_everywhere_route).send().unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // the cookie is now stale and issued before the new session epoch
+        let res = client.get(&private_route).send().unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_refresh_replaces_near_expiry_passport() {
+        ensure_server_running();
+
+        let register_route = format!("{ADDRESS}/accounts");
+        let refresh_route = format!("{ADDRESS}/refresh");
+        let client = reqwest::blocking::Client::new();
+
+        let account = AccountCreationInput {
+            username: "refresh_user".to_string(),
+            email: None,
+            password: "1234567890".to_string(),
+        };
+        client.post(register_route).json(&account).send().unwrap();
+
+        // a passport that's close to expiring, but still valid, is swapped
+        // for a brand new one
+        let near_expiry_issued_at = now() - (DEFAULT_TTL_SECONDS - 5);
+        let near_expiry = Kpassport::new_with_issued_at(
+            "refresh_user".to_string(),
+            vec!["subscriber".to_string()],
+            near_expiry_issued_at,
+        );
+        let res = client
+            .post(&refresh_route)
+            .header("Cookie", format!("kpassport={}", near_expiry.encode()))
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        let refreshed = Kpassport::decode(&kpassport_cookie(&res)).unwrap();
+        assert!(refreshed.issued_at > near_expiry_issued_at);
+
+        // a passport minted past its TTL is rejected outright, not renewed
+        let expired_issued_at = now() - (DEFAULT_TTL_SECONDS + 10);
+        let expired = Kpassport::new_with_issued_at(
+            "refresh_user".to_string(),
+            vec!["subscriber".to_string()],
+            expired_issued_at,
+        );
+        let res = client
+            .post(&refresh_route)
+            .header("Cookie", format!("kpassport={}", expired.encode()))
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_account_lifecycle() {
+        let _guard = serial_guard();
+        ensure_server_running();
+
+        let register_route = format!("{ADDRESS}/accounts");
+        let login_route = format!("{ADDRESS}/login");
+        let update_password_route = format!("{ADDRESS}/accounts/password");
+        let update_email_route = format!("{ADDRESS}/accounts/email");
+        let verify_route = format!("{ADDRESS}/accounts/verify");
+        let delete_route = format!("{ADDRESS}/accounts/delete");
+        let client = reqwest::blocking::Client::builder()
+            .cookie_store(true)
+            .build()
+            .unwrap();
+
+        let account = AccountCreationInput {
+            username: "lifecycle".to_string(),
+            email: Some("lifecycle@example.com".to_string()),
+            password: "1234567890".to_string(),
+        };
+        client.post(register_route).json(&account).send().unwrap();
+
+        let login_info = AccountLoginInput {
+            username: "lifecycle".to_string(),
+            password: "1234567890".to_string(),
+        };
+        client.post(login_route).json(&login_info).send().unwrap();
+
+        // changing the password requires the current one
+        let bad_update = UpdatePasswordInput {
+            current_password: "wrong_password".to_string(),
+            new_password: "0987654321".to_string(),
+        };
+        let res = client
+            .post(&update_password_route)
+            .json(&bad_update)
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let update = UpdatePasswordInput {
+            current_password: "1234567890".to_string(),
+            new_password: "0987654321".to_string(),
+        };
+        let res = client
+            .post(&update_password_route)
+            .json(&update)
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let update = UpdateEmailInput {
+            new_email: "updated@example.com".to_string(),
+        };
+        let res = client
+            .post(&update_email_route)
+            .json(&update)
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // changing the email re-triggers verification: a fresh token is
+        // minted for the new address and must be consumed before it
+        // counts as verified again
+        let token = LoggingMailer::last_token_for("updated@example.com")
+            .expect("email change sent a fresh verification token");
+        let res = client
+            .post(format!("{verify_route}?token={token}"))
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // deleting requires the (now updated) password as re-confirmation
+        let delete = DeleteAccountInput {
+            password: "0987654321".to_string(),
+        };
+        let res = client.post(&delete_route).json(&delete).send().unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_browser_client_redirected_to_login() {
+        ensure_server_running();
+
+        let private_route = format!("{ADDRESS}/private");
+        let client = reqwest::blocking::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+
+        // a browser hitting a private route while logged out bounces to the
+        // login page with the original path preserved as `redirect_to`
+        let res = client
+            .get(&private_route)
+            .header("Accept", "text/html")
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        let location = res.headers().get("Location").unwrap().to_str().unwrap();
+        assert_eq!(location, "/login?redirect_to=%2Fprivate");
+
+        // an API client keeps getting a plain 401, no redirect
+        let res = client
+            .get(&private_route)
+            .header("Accept", "application/json")
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn test_login_redirects_back_to_redirect_to_after_success() {
+        ensure_server_running();
+
+        let register_route = format!("{ADDRESS}/accounts");
+        let login_route = format!("{ADDRESS}/login?redirect_to=%2Fprivate");
+        let client = reqwest::blocking::Client::builder()
+            .cookie_store(true)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+
+        let account = AccountCreationInput {
+            username: "redirect_roundtrip".to_string(),
+            email: None,
+            password: "1234567890".to_string(),
+        };
+        client.post(register_route).json(&account).send().unwrap();
+
+        let login_info = AccountLoginInput {
+            username: "redirect_roundtrip".to_string(),
+            password: "1234567890".to_string(),
+        };
+        // a successful login carrying `redirect_to` bounces back to the
+        // original path instead of returning its usual 200
+        let res = client.post(&login_route).json(&login_info).send().unwrap();
+        assert_eq!(res.status(), StatusCode::FOUND);
+        let location = res.headers().get("Location").unwrap().to_str().unwrap();
+        assert_eq!(location, "/private");
+    }
+
+    #[test]
+    fn test_login_rejects_absolute_redirect_to() {
+        ensure_server_running();
+
+        let register_route = format!("{ADDRESS}/accounts");
+        let login_route = format!("{ADDRESS}/login?redirect_to=https%3A%2F%2Fevil.example");
+        let client = reqwest::blocking::Client::builder()
+            .cookie_store(true)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+
+        let account = AccountCreationInput {
+            username: "redirect_absolute".to_string(),
+            email: None,
+            password: "1234567890".to_string(),
+        };
+        client.post(register_route).json(&account).send().unwrap();
+
+        let login_info = AccountLoginInput {
+            username: "redirect_absolute".to_string(),
+            password: "1234567890".to_string(),
+        };
+        // an absolute/external `redirect_to` is never honored: a successful
+        // login just returns its normal 200, not a bounce to evil.example
+        let res = client.post(&login_route).json(&login_info).send().unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_login_rejects_protocol_relative_redirect_to() {
+        ensure_server_running();
+
+        let register_route = format!("{ADDRESS}/accounts");
+        // `//evil.example` has no scheme, so a naive `starts_with("http")`
+        // absolute-URL check would wave it through -- browsers still treat
+        // it as a fully external redirect
+        let login_route = format!("{ADDRESS}/login?redirect_to=%2F%2Fevil.example");
+        let client = reqwest::blocking::Client::builder()
+            .cookie_store(true)
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+
+        let account = AccountCreationInput {
+            username: "redirect_protocol_relative".to_string(),
+            email: None,
+            password: "1234567890".to_string(),
+        };
+        client.post(register_route).json(&account).send().unwrap();
+
+        let login_info = AccountLoginInput {
+            username: "redirect_protocol_relative".to_string(),
+            password: "1234567890".to_string(),
+        };
+        let res = client.post(&login_route).json(&login_info).send().unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
     fn remove_test_dbs() {
         let test_db_path = std::path::Path::new(TEST_ACCOUNTS_DB);
         if std::path::Path::exists(test_db_path) {
@@ -156,11 +583,17 @@ mod test {
         if std::path::Path::exists(test_db_path) {
             std::fs::remove_file(test_db_path).unwrap();
         }
+
+        let test_db_path = std::path::Path::new(TEST_NEWSLETTER_DB);
+        if std::path::Path::exists(test_db_path) {
+            std::fs::remove_file(test_db_path).unwrap();
+        }
     }
 
     #[test]
     fn test_create_blog_post() {
-        remove_test_dbs();
+        let _guard = serial_guard();
+        ensure_server_running();
 
         let register_route = format!("{ADDRESS}/accounts");
         let login_route = format!("{ADDRESS}/login");
@@ -206,9 +639,50 @@ mod test {
         assert_eq!(res.status(), StatusCode::CREATED);
     }
 
+    #[test]
+    fn test_create_blog_post_rejects_duplicate_slug() {
+        let _guard = serial_guard();
+        ensure_server_running();
+
+        let register_route = format!("{ADDRESS}/accounts");
+        let login_route = format!("{ADDRESS}/login");
+        let url = format!("{ADDRESS}/blog");
+        let form = || {
+            multipart::Form::new()
+                .text("title", "Duplicate Slug Post")
+                .text("subtitle", "")
+                .file("cover", "./test.png")
+                .unwrap()
+                .text("content", "content")
+        };
+
+        let client = reqwest::blocking::Client::builder()
+            .cookie_store(true)
+            .build()
+            .unwrap();
+        let account = AccountCreationInput {
+            username: "admin".to_string(),
+            email: Some("admin@example.com".to_string()),
+            password: "1234567890".to_string(),
+        };
+        client.post(register_route).json(&account).send().unwrap();
+        let login_info = AccountLoginInput {
+            username: "admin".to_string(),
+            password: "1234567890".to_string(),
+        };
+        client.post(login_route).json(&login_info).send().unwrap();
+
+        let res = client.post(&url).multipart(form()).send().unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+
+        // the same title slugifies to the same slug, which is unique
+        let res = client.post(&url).multipart(form()).send().unwrap();
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+    }
+
     #[test]
     fn test_subscribe_newsletter() {
-        remove_test_dbs();
+        ensure_server_running();
 
         let url = format!("{ADDRESS}/newsletter");
         let form = multipart::Form::new().text("email", "test@example.com");
@@ -217,4 +691,102 @@ mod test {
         let res = client.post(&url).multipart(form).send().unwrap();
         assert_eq!(res.status(), StatusCode::CREATED);
     }
+
+    #[test]
+    fn test_subscribe_newsletter_rejects_duplicate() {
+        ensure_server_running();
+
+        let url = format!("{ADDRESS}/newsletter");
+        let email = "duplicate_newsletter@example.com";
+        let client = reqwest::blocking::Client::builder().build().unwrap();
+
+        let form = multipart::Form::new().text("email", email);
+        let res = client.post(&url).multipart(form).send().unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+
+        let form = multipart::Form::new().text("email", email);
+        let res = client.post(&url).multipart(form).send().unwrap();
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+    }
+
+    #[test]
+    fn test_verify_newsletter_rejects_unknown_token() {
+        ensure_server_running();
+
+        let subscribe_route = format!("{ADDRESS}/newsletter");
+        let verify_route = format!("{ADDRESS}/newsletter/verify");
+        let form = multipart::Form::new().text("email", "verify@example.com");
+
+        let client = reqwest::blocking::Client::builder().build().unwrap();
+        client.post(subscribe_route).multipart(form).send().unwrap();
+
+        // a subscription starts out unverified and a bogus token is rejected
+        let res = client
+            .post(format!("{verify_route}?token=not-a-real-token"))
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_verify_newsletter_accepts_valid_token() {
+        ensure_server_running();
+
+        let subscribe_route = format!("{ADDRESS}/newsletter");
+        let verify_route = format!("{ADDRESS}/newsletter/verify");
+        let email = "verify_newsletter_ok@example.com";
+        let form = multipart::Form::new().text("email", email);
+
+        let client = reqwest::blocking::Client::builder().build().unwrap();
+        let res = client.post(subscribe_route).multipart(form).send().unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+
+        // the token minted for the original subscription flips it verified
+        let token = LoggingMailer::last_token_for(email).expect("mailer captured a token");
+        let res = client
+            .post(format!("{verify_route}?token={token}"))
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // the same token can't be replayed
+        let res = client
+            .post(format!("{verify_route}?token={token}"))
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_verify_account_accepts_valid_token() {
+        ensure_server_running();
+
+        let register_route = format!("{ADDRESS}/accounts");
+        let verify_route = format!("{ADDRESS}/accounts/verify");
+        let email = "verify_account_ok@example.com";
+
+        let client = reqwest::blocking::Client::builder().build().unwrap();
+        let account = AccountCreationInput {
+            username: "verify_account_ok".to_string(),
+            email: Some(email.to_string()),
+            password: "1234567890".to_string(),
+        };
+        let res = client.post(register_route).json(&account).send().unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+
+        // the token minted for the original registration flips it verified
+        let token = LoggingMailer::last_token_for(email).expect("mailer captured a token");
+        let res = client
+            .post(format!("{verify_route}?token={token}"))
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // a bogus token is rejected
+        let res = client
+            .post(format!("{verify_route}?token=not-a-real-token"))
+            .send()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
 }